@@ -6,26 +6,139 @@ pub struct Position {
     pub col: usize,
 }
 
+/// Mask covering the 16 squares of player one's zone (rows 0-3).
+pub const ZONE_ONE: u32 = 0x0000FFFF;
+/// Mask covering the 16 squares of player two's zone (rows 4-7).
+pub const ZONE_TWO: u32 = 0xFFFF0000;
+
+/// The 8x4 field is exactly 32 squares, so the position is stored as three
+/// `u32` masks - one per piece type - where bit `4*row + col` is set when that
+/// piece occupies the square. Occupancy is the OR of the three.
 pub struct Board {
-    pub data: Vec<Option<Piece>>,
+    pub pawns: u32,
+    pub drones: u32,
+    pub queens: u32,
     pub rows: usize,
     pub cols: usize,
 }
 
 impl Board {
     pub fn new(rows: usize, cols: usize) -> Self {
-        Self{rows, cols, data: vec![None; rows*cols]}
+        Self { rows, cols, pawns: 0, drones: 0, queens: 0 }
     }
 
     pub fn position_to_index(&self, p: Position) -> usize {
         self.cols * p.row + p.col
     }
 
-    pub fn get_piece(&self, Position { row, col }: Position) -> Option<Piece> {
-        self.data[self.cols * row + col]
+    pub fn occupancy(&self) -> u32 {
+        self.pawns | self.drones | self.queens
+    }
+
+    pub fn mask_for(&self, piece: Piece) -> u32 {
+        match piece {
+            Piece::Pawn => self.pawns,
+            Piece::Drone => self.drones,
+            Piece::Queen => self.queens,
+        }
+    }
+
+    pub fn piece_at(&self, index: usize) -> Option<Piece> {
+        let bit = 1_u32 << index;
+        if self.pawns & bit != 0 {
+            Some(Piece::Pawn)
+        } else if self.drones & bit != 0 {
+            Some(Piece::Drone)
+        } else if self.queens & bit != 0 {
+            Some(Piece::Queen)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_piece(&self, p: Position) -> Option<Piece> {
+        self.piece_at(self.position_to_index(p))
     }
 
-    pub fn get_piece_mut(&mut self, Position { row, col }: Position) -> &mut Option<Piece> {
-        &mut self.data[self.cols * row + col]
+    pub fn set_piece(&mut self, p: Position, val: Option<Piece>) {
+        let bit = 1_u32 << self.position_to_index(p);
+        self.pawns &= !bit;
+        self.drones &= !bit;
+        self.queens &= !bit;
+        match val {
+            Some(Piece::Pawn) => self.pawns |= bit,
+            Some(Piece::Drone) => self.drones |= bit,
+            Some(Piece::Queen) => self.queens |= bit,
+            None => (),
+        }
+    }
+
+    /// Encode the field top to bottom using the `print()` glyphs, run-length
+    /// digits for consecutive empty squares, and `/` between rows.
+    pub fn to_notation(&self) -> String {
+        let mut out = String::new();
+        for row in 0..self.rows {
+            let mut empty = 0;
+            for col in 0..self.cols {
+                match self.get_piece(Position { row, col }) {
+                    None => empty += 1,
+                    Some(piece) => {
+                        if empty > 0 {
+                            out.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        out.push(match piece {
+                            Piece::Pawn => 'P',
+                            Piece::Drone => 'D',
+                            Piece::Queen => 'Q',
+                        });
+                    }
+                }
+            }
+            if empty > 0 {
+                out.push_str(&empty.to_string());
+            }
+            if row + 1 < self.rows {
+                out.push('/');
+            }
+        }
+        return out;
+    }
+
+    pub fn from_notation(s: &str) -> Result<Board, &'static str> {
+        let mut board = Board::new(8, 4);
+        let rows: Vec<&str> = s.split('/').collect();
+        if rows.len() != board.rows {
+            return Err("Notation must describe 8 rows");
+        }
+
+        for (row, row_str) in rows.iter().enumerate() {
+            let mut col = 0;
+            for ch in row_str.chars() {
+                if let Some(d) = ch.to_digit(10) {
+                    col += d as usize;
+                    if col > board.cols {
+                        return Err("Row overflows 4 columns");
+                    }
+                } else {
+                    let piece = match ch {
+                        'P' => Piece::Pawn,
+                        'D' => Piece::Drone,
+                        'Q' => Piece::Queen,
+                        _ => return Err("Invalid piece in notation"),
+                    };
+                    if col >= board.cols {
+                        return Err("Row overflows 4 columns");
+                    }
+                    board.set_piece(Position { row, col }, Some(piece));
+                    col += 1;
+                }
+            }
+            if col != board.cols {
+                return Err("Row does not fill 4 columns");
+            }
+        }
+
+        return Ok(board);
     }
 }
@@ -1,10 +1,14 @@
+use std::collections::HashMap;
 use std::error::Error;
 use std::io;
 use std::io::Write;
+use std::sync::OnceLock;
 
 use crate::pieces::Piece;
 use crate::board::Board;
 use crate::board::Position;
+use crate::board::ZONE_ONE;
+use crate::board::ZONE_TWO;
 
 use regex::Regex;
 
@@ -12,11 +16,38 @@ pub mod pieces;
 pub mod board;
 
 pub struct Config {
+    ai: Option<u32>,
+    load: Option<String>,
 }
 
 impl Config {
     pub fn build(mut args: impl Iterator<Item = String>) -> Result<Self, &'static str> {
-        Ok(Config {})
+        args.next();
+
+        let mut ai = None;
+        let mut load = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--ai" => {
+                    let Some(p) = args.next() else {
+                        return Err("--ai requires a player number");
+                    };
+                    match p.parse::<u32>() {
+                        Ok(p @ (1 | 2)) => ai = Some(p),
+                        _ => return Err("--ai must be 1 or 2"),
+                    }
+                }
+                "--load" => {
+                    let Some(s) = args.next() else {
+                        return Err("--load requires a board string");
+                    };
+                    load = Some(s);
+                }
+                _ => return Err("Unrecognized argument"),
+            }
+        }
+
+        Ok(Config { ai, load })
     }
 }
 
@@ -33,12 +64,173 @@ struct Move {
     dst: Position,
 }
 
+fn move_to_string(m: &Move) -> String {
+    return format!(
+        "{}{}{}{}",
+        (b'a' + m.src.col as u8) as char,
+        m.src.row + 1,
+        (b'a' + m.dst.col as u8) as char,
+        m.dst.row + 1
+    );
+}
+
+fn parse_move(input: &str) -> Option<Move> {
+    let re = Regex::new("([a-dA-D])([1-8])([a-dA-D])([1-8])").unwrap();
+    match re.captures(input) {
+        Some(caps) => Some(Move {
+            src: Position {
+                row: caps[2].parse::<usize>().unwrap() - 1,
+                col: ((u32::from(caps[1].chars().nth(0).unwrap()) | 32) - u32::from('a')) as usize,
+            },
+            dst: Position {
+                row: caps[4].parse::<usize>().unwrap() - 1,
+                col: ((u32::from(caps[3].chars().nth(0).unwrap()) | 32) - u32::from('a')) as usize,
+            },
+        }),
+        None => None,
+    }
+}
+
+/// Fixed table of random keys backing the incremental Zobrist hash: one key
+/// per (square, piece-type) pair plus a single key toggled when it is player
+/// two's turn, since the zone-of-control rules make the side to move part of
+/// the position's identity.
+struct Zobrist {
+    pieces: [u64; 96],
+    side: u64,
+}
+
+impl Zobrist {
+    fn piece_key(&self, square: usize, piece: Piece) -> u64 {
+        let offset = match piece {
+            Piece::Pawn => 0,
+            Piece::Drone => 1,
+            Piece::Queen => 2,
+        };
+        return self.pieces[3 * square + offset];
+    }
+}
+
+/// Build the Zobrist keys once from a seeded splitmix64 stream so that hashes
+/// are identical from run to run.
+fn zobrist() -> &'static Zobrist {
+    static TABLE: OnceLock<Zobrist> = OnceLock::new();
+    return TABLE.get_or_init(|| {
+        let mut state = 0x9E3779B97F4A7C15_u64;
+        let mut next = || {
+            state = state.wrapping_add(0x9E3779B97F4A7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+            return z ^ (z >> 31);
+        };
+
+        let mut pieces = [0_u64; 96];
+        for key in pieces.iter_mut() {
+            *key = next();
+        }
+        Zobrist {
+            pieces,
+            side: next(),
+        }
+    });
+}
+
+/// Precomputed per-square move geometry. `pawn`/`drone`/`queen` hold the set of
+/// destination squares each piece type could reach from a square on an empty
+/// board; `between[32*src + dst]` holds the squares strictly between two
+/// collinear squares, so a single AND against the occupancy answers whether a
+/// sliding move is blocked.
+struct Geometry {
+    pawn: [u32; 32],
+    drone: [u32; 32],
+    queen: [u32; 32],
+    between: [u32; 32 * 32],
+}
+
+fn geometry() -> &'static Geometry {
+    static GEOM: OnceLock<Geometry> = OnceLock::new();
+    return GEOM.get_or_init(|| {
+        let mut g = Geometry {
+            pawn: [0; 32],
+            drone: [0; 32],
+            queen: [0; 32],
+            between: [0; 32 * 32],
+        };
+
+        for src in 0..32 {
+            let s = Position { row: src / 4, col: src % 4 };
+            for dst in 0..32 {
+                if src == dst {
+                    continue;
+                }
+                let d = Position { row: dst / 4, col: dst % 4 };
+
+                if Piece::Pawn.validate_move(&s, &d).is_ok() {
+                    g.pawn[src] |= 1 << dst;
+                }
+                if Piece::Drone.validate_move(&s, &d).is_ok() {
+                    g.drone[src] |= 1 << dst;
+                }
+                if Piece::Queen.validate_move(&s, &d).is_ok() {
+                    g.queen[src] |= 1 << dst;
+                }
+
+                let dr = d.row as i32 - s.row as i32;
+                let dc = d.col as i32 - s.col as i32;
+                if dr == 0 || dc == 0 || dr.abs() == dc.abs() {
+                    let step_r = dr.signum();
+                    let step_c = dc.signum();
+                    let mut r = s.row as i32 + step_r;
+                    let mut c = s.col as i32 + step_c;
+                    let mut mask = 0_u32;
+                    while (r, c) != (d.row as i32, d.col as i32) {
+                        mask |= 1 << (4 * r + c);
+                        r += step_r;
+                        c += step_c;
+                    }
+                    g.between[32 * src + dst] = mask;
+                }
+            }
+        }
+
+        g
+    });
+}
+
+#[derive(Copy, Clone)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+struct TtEntry {
+    depth: u32,
+    score: f32,
+    bound: Bound,
+    best_move: Option<Move>,
+}
+
+struct Undo {
+    captured: Option<Piece>,
+    promoted_from: Option<(Piece, Piece)>,
+    score_delta: (u8, u8),
+    prev_move: Option<Move>,
+    clock_before: i32,
+    turn_before: u32,
+}
+
 struct MartianChess {
     board: Board,
     turn: u32,
     score: (u8, u8),
     prev_move: Option<Move>,
     clock: i32,
+    ai: Option<u32>,
+    hash: u64,
+    history: HashMap<u64, u32>,
+    tt: HashMap<u64, TtEntry>,
 }
 
 impl MartianChess {
@@ -49,32 +241,141 @@ impl MartianChess {
             score: (0, 0),
             prev_move: None,
             clock: -1,
+            ai: config.ai,
+            hash: 0,
+            history: HashMap::new(),
+            tt: HashMap::new(),
         };
 
-        *game.board.get_piece_mut(Position{row: 1, col: 2}) = Some(Piece::Pawn);
-        *game.board.get_piece_mut(Position{row: 2, col: 1}) = Some(Piece::Pawn);
-        *game.board.get_piece_mut(Position{row: 2, col: 2}) = Some(Piece::Pawn);
-        *game.board.get_piece_mut(Position{row: 5, col: 1}) = Some(Piece::Pawn);
-        *game.board.get_piece_mut(Position{row: 5, col: 2}) = Some(Piece::Pawn);
-        *game.board.get_piece_mut(Position{row: 6, col: 1}) = Some(Piece::Pawn);
-
-        *game.board.get_piece_mut(Position{row: 0, col: 2}) = Some(Piece::Drone);
-        *game.board.get_piece_mut(Position{row: 1, col: 1}) = Some(Piece::Drone);
-        *game.board.get_piece_mut(Position{row: 2, col: 0}) = Some(Piece::Drone);
-        *game.board.get_piece_mut(Position{row: 5, col: 3}) = Some(Piece::Drone);
-        *game.board.get_piece_mut(Position{row: 6, col: 2}) = Some(Piece::Drone);
-        *game.board.get_piece_mut(Position{row: 7, col: 1}) = Some(Piece::Drone);
+        game.board.set_piece(Position{row: 1, col: 2}, Some(Piece::Pawn));
+        game.board.set_piece(Position{row: 2, col: 1}, Some(Piece::Pawn));
+        game.board.set_piece(Position{row: 2, col: 2}, Some(Piece::Pawn));
+        game.board.set_piece(Position{row: 5, col: 1}, Some(Piece::Pawn));
+        game.board.set_piece(Position{row: 5, col: 2}, Some(Piece::Pawn));
+        game.board.set_piece(Position{row: 6, col: 1}, Some(Piece::Pawn));
+
+        game.board.set_piece(Position{row: 0, col: 2}, Some(Piece::Drone));
+        game.board.set_piece(Position{row: 1, col: 1}, Some(Piece::Drone));
+        game.board.set_piece(Position{row: 2, col: 0}, Some(Piece::Drone));
+        game.board.set_piece(Position{row: 5, col: 3}, Some(Piece::Drone));
+        game.board.set_piece(Position{row: 6, col: 2}, Some(Piece::Drone));
+        game.board.set_piece(Position{row: 7, col: 1}, Some(Piece::Drone));
         
-        *game.board.get_piece_mut(Position{row: 0, col: 0}) = Some(Piece::Queen);
-        *game.board.get_piece_mut(Position{row: 0, col: 1}) = Some(Piece::Queen);
-        *game.board.get_piece_mut(Position{row: 1, col: 0}) = Some(Piece::Queen);
-        *game.board.get_piece_mut(Position{row: 6, col: 3}) = Some(Piece::Queen);
-        *game.board.get_piece_mut(Position{row: 7, col: 2}) = Some(Piece::Queen);
-        *game.board.get_piece_mut(Position{row: 7, col: 3}) = Some(Piece::Queen);
+        game.board.set_piece(Position{row: 0, col: 0}, Some(Piece::Queen));
+        game.board.set_piece(Position{row: 0, col: 1}, Some(Piece::Queen));
+        game.board.set_piece(Position{row: 1, col: 0}, Some(Piece::Queen));
+        game.board.set_piece(Position{row: 6, col: 3}, Some(Piece::Queen));
+        game.board.set_piece(Position{row: 7, col: 2}, Some(Piece::Queen));
+        game.board.set_piece(Position{row: 7, col: 3}, Some(Piece::Queen));
+
+        game.hash = game.compute_hash();
+        *game.history.entry(game.position_key()).or_insert(0) += 1;
+
+        if let Some(s) = config.load {
+            if let Err(e) = game.load_notation(&s) {
+                eprintln!("Could not load position ({}); starting from the opening setup", e);
+            }
+        }
 
         return game;
     }
 
+    /// Serialize the whole game - board plus turn, score, clock and the
+    /// previous move - so a position can round-trip through `load_notation`.
+    fn to_notation(&self) -> String {
+        let prev = match self.prev_move {
+            Some(m) if m.src.row != 32 => move_to_string(&m),
+            _ => String::from("-"),
+        };
+        return format!(
+            "{} {} {} {} {} {}",
+            self.board.to_notation(),
+            self.turn,
+            self.score.0,
+            self.score.1,
+            self.clock,
+            prev
+        );
+    }
+
+    fn load_notation(&mut self, s: &str) -> Result<(), &'static str> {
+        let mut fields = s.split_whitespace();
+
+        let board = Board::from_notation(fields.next().ok_or("Missing board")?)?;
+        let turn = fields
+            .next()
+            .and_then(|f| f.parse::<u32>().ok())
+            .ok_or("Missing or invalid turn")?;
+        let score_0 = fields
+            .next()
+            .and_then(|f| f.parse::<u8>().ok())
+            .ok_or("Missing or invalid score")?;
+        let score_1 = fields
+            .next()
+            .and_then(|f| f.parse::<u8>().ok())
+            .ok_or("Missing or invalid score")?;
+        let clock = fields
+            .next()
+            .and_then(|f| f.parse::<i32>().ok())
+            .ok_or("Missing or invalid clock")?;
+        let prev_move = match fields.next().ok_or("Missing previous move")? {
+            "-" => None,
+            m => Some(parse_move(m).ok_or("Invalid previous move")?),
+        };
+
+        self.board = board;
+        self.turn = turn;
+        self.score = (score_0, score_1);
+        self.clock = clock;
+        self.prev_move = prev_move;
+
+        self.hash = self.compute_hash();
+        self.history.clear();
+        *self.history.entry(self.position_key()).or_insert(0) += 1;
+        self.tt.clear();
+
+        return Ok(());
+    }
+
+    /// Key identifying a full position for the transposition table and the
+    /// repetition history. The incremental Zobrist hash only covers piece
+    /// placement and side to move, but `evaluate` and terminality also depend
+    /// on the captured-point split and the clock, so those are mixed in to
+    /// keep positions that differ only in score or clock distinct.
+    fn position_key(&self) -> u64 {
+        let mut key = self.hash;
+        key ^= (self.score.0 as u64).wrapping_mul(0x2545F4914F6CDD1D);
+        key ^= (self.score.1 as u64).wrapping_mul(0x9E3779B97F4A7C15);
+        key ^= (self.clock as i64 as u64).wrapping_mul(0xBF58476D1CE4E5B9);
+        return key;
+    }
+
+    fn compute_hash(&self) -> u64 {
+        let z = zobrist();
+        let mut hash = 0_u64;
+        for i in 0..32 {
+            if let Some(p) = self.board.piece_at(i) {
+                hash ^= z.piece_key(i, p);
+            }
+        }
+        if self.turn % 2 == 1 {
+            hash ^= z.side;
+        }
+        return hash;
+    }
+
+    fn set_square(&mut self, p: Position, val: Option<Piece>) {
+        let z = zobrist();
+        let idx = self.board.position_to_index(p);
+        if let Some(old) = self.board.get_piece(p) {
+            self.hash ^= z.piece_key(idx, old);
+        }
+        if let Some(new) = val {
+            self.hash ^= z.piece_key(idx, new);
+        }
+        self.board.set_piece(p, val);
+    }
+
 
     fn get_zone(&self, Position { row, .. }: Position) -> u32 {
         if row <= 3 {
@@ -90,7 +391,7 @@ impl MartianChess {
             if self.clock > 0 {
                 return Err("Clock already started");
             } else {
-                self.clock = 8;
+                self.do_move(m);
                 return Ok(());
             }
         }
@@ -104,7 +405,7 @@ impl MartianChess {
             // Make sure the piece is in your zone of control
             return Err("Source Position is not in Player zone of control");
         }
-        
+
         if m.src == m.dst {
             return Err("Must move piece to location different from starting location");
         }
@@ -117,53 +418,97 @@ impl MartianChess {
 
         src_piece.validate_move(&m.src, &m.dst)?;
         self.collision_check(&m)?;
-        if self.turn % 2 != self.get_zone(m.dst) {
-            // Moving
-            if self.clock > 0 && !self.board.get_piece(m.dst).is_none() {
-                self.clock = 8;
-            }
 
-            if self.turn % 2 == 0 {
-                if let Some(p) = self.board.get_piece(m.dst) {
-                    self.score.0 += p.points();
+        // A promotion is the only way to land on an occupied square in our own
+        // zone; check it here so do_move may assume the move is legal.
+        if self.turn % 2 == self.get_zone(m.dst) && self.board.get_piece(m.dst).is_some() {
+            self.can_promote(&m)?;
+        }
+
+        self.do_move(m);
+        return Ok(());
+    }
+
+    fn do_move(&mut self, m: &Move) -> Undo {
+        let mut u = Undo {
+            captured: None,
+            promoted_from: None,
+            score_delta: (0, 0),
+            prev_move: self.prev_move,
+            clock_before: self.clock,
+            turn_before: self.turn,
+        };
+
+        if m.src.row == 32 {
+            self.clock = 8;
+            return u;
+        }
+
+        let src_piece = self.board.get_piece(m.src).unwrap();
+
+        if self.turn % 2 != self.get_zone(m.dst) {
+            // Moving into the opposing zone, capturing whatever is there.
+            if let Some(p) = self.board.get_piece(m.dst) {
+                if self.clock > 0 {
+                    self.clock = 8;
                 }
-            } else {
-                if let Some(p) = self.board.get_piece(m.dst) {
+                u.captured = Some(p);
+                if self.turn % 2 == 0 {
+                    self.score.0 += p.points();
+                    u.score_delta = (p.points(), 0);
+                } else {
                     self.score.1 += p.points();
+                    u.score_delta = (0, p.points());
                 }
             }
-            *self.board.get_piece_mut(m.dst) = self.board.get_piece(m.src);
+            self.set_square(m.dst, Some(src_piece));
         } else {
-            if self.board.get_piece(m.dst).is_none() {
-                *self.board.get_piece_mut(m.dst) = self.board.get_piece(m.src);
-            } else {
-                let new_piece = self.can_promote(&m)?;
-                *self.board.get_piece_mut(m.dst) = Some(new_piece);
+            match self.board.get_piece(m.dst) {
+                None => self.set_square(m.dst, Some(src_piece)),
+                Some(dst_piece) => {
+                    let new_piece = src_piece.promote(dst_piece).unwrap();
+                    u.promoted_from = Some((src_piece, dst_piece));
+                    self.set_square(m.dst, Some(new_piece));
+                }
             }
         }
-        *self.board.get_piece_mut(m.src) = None;
-        self.turn += 1;
 
+        self.set_square(m.src, None);
+        self.turn += 1;
+        self.hash ^= zobrist().side;
         self.prev_move = Some(*m);
-        return Ok(());
+        return u;
     }
 
-    fn collision_check(&self, m: &Move) -> Result<(), &'static str> {
-        let dx = (m.dst.row as i32 - m.src.row as i32).signum();
-        let dy = (m.dst.col as i32 - m.src.col as i32).signum();
+    fn undo_move(&mut self, m: &Move, u: Undo) {
+        self.clock = u.clock_before;
+        self.turn = u.turn_before;
+        self.prev_move = u.prev_move;
+        self.score.0 -= u.score_delta.0;
+        self.score.1 -= u.score_delta.1;
 
-        let mut n = Position {
-            row: (m.src.row as i32 + dx) as usize,
-            col: (m.src.col as i32 + dy) as usize,
-        };
+        if m.src.row == 32 {
+            return;
+        }
 
-        while n != m.dst {
-            if !self.board.get_piece(n).is_none() {
-                return Err("Move blocked by");
-            } else {
-                n.row = (n.row as i32 + dx) as usize;
-                n.col = (n.col as i32 + dy) as usize;
-            }
+        self.hash ^= zobrist().side;
+
+        if let Some((src_piece, dst_piece)) = u.promoted_from {
+            // Split the promoted Queen back into the two pieces that merged.
+            self.set_square(m.src, Some(src_piece));
+            self.set_square(m.dst, Some(dst_piece));
+        } else {
+            let moved = self.board.get_piece(m.dst);
+            self.set_square(m.src, moved);
+            self.set_square(m.dst, u.captured);
+        }
+    }
+
+    fn collision_check(&self, m: &Move) -> Result<(), &'static str> {
+        let src = self.board.position_to_index(m.src);
+        let dst = self.board.position_to_index(m.dst);
+        if geometry().between[32 * src + dst] & self.board.occupancy() != 0 {
+            return Err("Move blocked by");
         }
 
         return Ok(());
@@ -180,39 +525,14 @@ impl MartianChess {
 
         let new_piece = src_piece.promote(dst_piece)?;
 
-        let mut cnt = 0;
-        for i in 0..32 {
-            if let Some(p) = self.board.data[i] {
-                if p == new_piece {
-                    cnt += 1;
-                }
-            }
-        }
+        let type_mask = self.board.mask_for(new_piece);
 
-        if cnt > 5 {
+        if type_mask.count_ones() > 5 {
             return Err("Cannot have more than 6 of one piece on the board");
         }
 
-        let mut cnt = 0;
-        if self.turn % 2 == 0 {
-            for i in 0..16 {
-                if let Some(p) = self.board.data[i] {
-                    if p == new_piece {
-                        cnt += 1;
-                    }
-                }
-            }
-        } else {
-            for i in 16..32 {
-                if let Some(p) = self.board.data[i] {
-                    if p == new_piece {
-                        cnt += 1;
-                    }
-                }
-            }
-        }
-
-        if cnt > 1 {
+        let zone = if self.turn % 2 == 0 { ZONE_ONE } else { ZONE_TWO };
+        if (type_mask & zone).count_ones() > 1 {
             return Err("Cannot promote if piece type already in your zone");
         }
 
@@ -220,21 +540,9 @@ impl MartianChess {
     }
 
     fn can_play(&self) -> bool {
-        let mut t1 = 0;
-        for i in 0..16 {
-            match self.board.data[i] {
-                Some(_) => t1 += 1,
-                None => (),
-            }
-        }
-
-        let mut t2 = 0;
-        for i in 16..32 {
-            match self.board.data[i] {
-                Some(_) => t2 += 1,
-                None => (),
-            }
-        }
+        let occ = self.board.occupancy();
+        let t1 = (occ & ZONE_ONE).count_ones();
+        let t2 = (occ & ZONE_TWO).count_ones();
 
         if t1 == 0 {
             println!("Game Over: No pieces in zone 1");
@@ -251,9 +559,198 @@ impl MartianChess {
             return false;
         }
 
+        if self.is_draw() {
+            println!("Game Over: Threefold repetition");
+            return false;
+        }
+
         return true;
     }
 
+    fn is_draw(&self) -> bool {
+        return self.history.get(&self.position_key()).copied().unwrap_or(0) >= 3;
+    }
+
+    fn generate_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+
+        if self.clock == -1 {
+            moves.push(Move {
+                src: Position { row: 32, col: 32 },
+                dst: Position { row: 32, col: 32 },
+            });
+        }
+
+        let g = geometry();
+        let occ = self.board.occupancy();
+
+        for row in 0..8 {
+            for col in 0..4 {
+                let src = Position { row, col };
+                if self.turn % 2 != self.get_zone(src) {
+                    continue;
+                }
+                let Some(src_piece) = self.board.get_piece(src) else {
+                    continue;
+                };
+
+                let src_idx = self.board.position_to_index(src);
+                let mut dests = match src_piece {
+                    Piece::Pawn => g.pawn[src_idx],
+                    Piece::Drone => g.drone[src_idx],
+                    Piece::Queen => g.queen[src_idx],
+                };
+
+                while dests != 0 {
+                    let dst_idx = dests.trailing_zeros() as usize;
+                    dests &= dests - 1;
+
+                    // A single AND against occupancy tells us if the slide is
+                    // blocked before the destination.
+                    if g.between[32 * src_idx + dst_idx] & occ != 0 {
+                        continue;
+                    }
+
+                    let dst = Position { row: dst_idx / 4, col: dst_idx % 4 };
+                    let m = Move { src, dst };
+
+                    if let Some(p) = self.prev_move {
+                        if src == p.dst && dst == p.src {
+                            continue;
+                        }
+                    }
+
+                    // An occupied square in our own zone is only reachable
+                    // as a promotion, which has its own legality rules.
+                    if self.turn % 2 == self.get_zone(dst)
+                        && self.board.get_piece(dst).is_some()
+                        && self.can_promote(&m).is_err()
+                    {
+                        continue;
+                    }
+
+                    moves.push(m);
+                }
+            }
+        }
+
+        return moves;
+    }
+
+    fn game_over(&self) -> bool {
+        let occ = self.board.occupancy();
+        let t1 = (occ & ZONE_ONE).count_ones();
+        let t2 = (occ & ZONE_TWO).count_ones();
+        return t1 == 0 || t2 == 0 || self.clock == 0;
+    }
+
+    fn evaluate(&self) -> f32 {
+        let player = self.turn % 2;
+        let (mine, theirs) = if player == 0 {
+            (self.score.0, self.score.1)
+        } else {
+            (self.score.1, self.score.0)
+        };
+
+        let mut value = mine as f32 - theirs as f32;
+
+        // Material still sitting in the opponent's zone is what we push into to
+        // capture, so weight it lightly as a positional target.
+        let opp_zone = if player == 0 { ZONE_TWO } else { ZONE_ONE };
+        let target = (self.board.pawns & opp_zone).count_ones()
+            + 2 * (self.board.drones & opp_zone).count_ones()
+            + 3 * (self.board.queens & opp_zone).count_ones();
+        value += 0.1 * target as f32;
+
+        // In play() a tie on captured points is awarded to the non-moving
+        // player, so an even score slightly favours our opponent.
+        if self.score.0 == self.score.1 {
+            value -= 0.5;
+        }
+
+        return value;
+    }
+
+    fn negamax(&mut self, depth: u32, mut alpha: f32, mut beta: f32) -> (f32, Option<Move>) {
+        if depth == 0 || self.game_over() {
+            return (self.evaluate(), None);
+        }
+
+        let alpha_orig = alpha;
+        if let Some(e) = self.tt.get(&self.position_key()) {
+            if e.depth >= depth {
+                match e.bound {
+                    Bound::Exact => return (e.score, e.best_move),
+                    Bound::Lower => alpha = alpha.max(e.score),
+                    Bound::Upper => beta = beta.min(e.score),
+                }
+                if alpha >= beta {
+                    return (e.score, e.best_move);
+                }
+            }
+        }
+
+        let mut best_score = f32::NEG_INFINITY;
+        let mut best_move = None;
+
+        for m in self.generate_moves() {
+            let u = self.do_move(&m);
+            if self.clock > 0 {
+                self.clock -= 1;
+            }
+
+            // The clk pseudo-move starts the clock without handing over the
+            // turn, so the child is still scored from our side - recurse with
+            // the same window and don't negate.
+            let score = if m.src.row == 32 {
+                self.negamax(depth - 1, alpha, beta).0
+            } else {
+                -self.negamax(depth - 1, -beta, -alpha).0
+            };
+
+            self.undo_move(&m, u);
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(m);
+            }
+
+            if best_score > alpha {
+                alpha = best_score;
+            }
+
+            if best_score >= beta {
+                break;
+            }
+        }
+
+        let bound = if best_score <= alpha_orig {
+            Bound::Upper
+        } else if best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.tt.insert(
+            self.position_key(),
+            TtEntry {
+                depth,
+                score: best_score,
+                bound,
+                best_move,
+            },
+        );
+
+        return (best_score, best_move);
+    }
+
+    fn is_ai_turn(&self) -> bool {
+        match self.ai {
+            Some(p) => (self.turn % 2) as u32 == p - 1,
+            None => false,
+        }
+    }
+
     fn print(&self) {
         println!(" |ABCD");
         println!("-+----");
@@ -279,54 +776,64 @@ impl MartianChess {
         }
     }
 
-    fn get_move(&self) -> Option<Move> {
-        if self.clock > -1 {
-            print!("Player {} ({})> ", (self.turn % 2) + 1, self.clock);
-        } else {
-            print!("Player {}> ", (self.turn % 2) + 1);
-        }
-        std::io::stdout().flush().unwrap();
+    fn get_move(&mut self) -> Option<Move> {
+        let clk = Regex::new("\\s*clk\\s*").unwrap();
+        let dump = Regex::new("\\s*dump\\s*").unwrap();
+        let load = Regex::new("\\s*load\\s+(.+)").unwrap();
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).expect("");
+        loop {
+            if self.clock > -1 {
+                print!("Player {} ({})> ", (self.turn % 2) + 1, self.clock);
+            } else {
+                print!("Player {}> ", (self.turn % 2) + 1);
+            }
+            std::io::stdout().flush().unwrap();
 
-        if self.clock == -1 {
-            let re = Regex::new("\\s*clk\\s*").unwrap();
-            if re.is_match(&input) {
+            let mut input = String::new();
+            io::stdin().read_line(&mut input).expect("");
+
+            if self.clock == -1 && clk.is_match(&input) {
                 return Some(Move {
                     src: Position { row: 32, col: 32 },
                     dst: Position { row: 32, col: 32 },
                 });
             }
-        }
 
-        let re = Regex::new("([a-dA-D])([1-8])([a-dA-D])([1-8])").unwrap();
-        match re.captures(&input) {
-            Some(caps) => Some(Move {
-                src: Position {
-                    row: caps[2].parse::<usize>().unwrap() - 1,
-                    col: ((u32::from(caps[1].chars().nth(0).unwrap()) | 32) - u32::from('a'))
-                        as usize,
-                },
-                dst: Position {
-                    row: caps[4].parse::<usize>().unwrap() - 1,
-                    col: ((u32::from(caps[3].chars().nth(0).unwrap()) | 32) - u32::from('a'))
-                        as usize,
-                },
-            }),
-            None => None,
+            if dump.is_match(&input) {
+                println!("{}", self.to_notation());
+                continue;
+            }
+
+            if let Some(caps) = load.captures(&input) {
+                match self.load_notation(caps[1].trim()) {
+                    Ok(_) => self.print(),
+                    Err(e) => println!("{}", e),
+                }
+                continue;
+            }
+
+            return parse_move(&input);
         }
     }
 
     fn play(&mut self) {
         while self.can_play() {
             self.print();
-            if let Some(m) = self.get_move() {
+            let choice = if self.is_ai_turn() {
+                let (_, m) = self.negamax(4, f32::NEG_INFINITY, f32::INFINITY);
+                m.or_else(|| self.generate_moves().into_iter().find(|mv| mv.src.row != 32))
+            } else {
+                self.get_move()
+            };
+            if let Some(m) = choice {
                 match self.move_piece(&m) {
                     Ok(_) => {
                         if self.clock > 0 {
                             self.clock -= 1;
                         }
+                        if m.src.row != 32 {
+                            *self.history.entry(self.position_key()).or_insert(0) += 1;
+                        }
                     }
                     Err(s) => println!("{}", s),
                 };